@@ -0,0 +1,138 @@
+//! Concrete `MessagingLayer` used to stream head-status updates to light clients, so they
+//! can follow the chain tip without pulling and verifying every full block via the
+//! `DataAvailabilityLayer`.
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use avalanche_types::{choices, ids};
+use movement_sdk::MessagingLayer;
+use tokio::sync::{broadcast, RwLock};
+
+use crate::util::types::block::Block;
+
+/// A lightweight header, cheap enough to emit on every head-status change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub id: ids::Id,
+    pub parent_id: ids::Id,
+    pub height: u64,
+    pub timestamp: u64,
+}
+
+impl BlockHeader {
+    pub fn from_block(block: &Block) -> Self {
+        Self {
+            id: block.id(),
+            parent_id: block.parent_id(),
+            height: block.height(),
+            timestamp: block.timestamp(),
+        }
+    }
+}
+
+/// The two kinds of head-status update a full node emits.
+#[derive(Debug, Clone)]
+pub enum HeadUpdate {
+    /// The latest verified-but-not-yet-accepted (preferred) block header.
+    OptimisticUpdate(BlockHeader),
+    /// The latest accepted block header, with its status commitment.
+    FinalityUpdate {
+        header: BlockHeader,
+        commitment: choices::status::Status,
+    },
+}
+
+/// Tracks chain head purely from `HeadUpdate`s, without pulling or verifying full blocks.
+/// A full node sends through this via `send_message`; a node running in light mode tracks
+/// the tip through `receive_message` alone.
+#[derive(Clone)]
+pub struct LightClientHead {
+    finalized: Arc<RwLock<Option<BlockHeader>>>,
+    optimistic: Arc<RwLock<Option<BlockHeader>>>,
+    outgoing: broadcast::Sender<HeadUpdate>,
+}
+
+impl LightClientHead {
+    pub fn new() -> Self {
+        let (outgoing, _) = broadcast::channel(64);
+        Self {
+            finalized: Arc::new(RwLock::new(None)),
+            optimistic: Arc::new(RwLock::new(None)),
+            outgoing,
+        }
+    }
+
+    /// Subscribes to the stream of head updates this node emits.
+    pub fn subscribe(&self) -> broadcast::Receiver<HeadUpdate> {
+        self.outgoing.subscribe()
+    }
+
+    pub async fn finalized_head(&self) -> Option<BlockHeader> {
+        self.finalized.read().await.clone()
+    }
+
+    pub async fn optimistic_head(&self) -> Option<BlockHeader> {
+        self.optimistic.read().await.clone()
+    }
+}
+
+impl Default for LightClientHead {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MessagingLayer for LightClientHead {
+    type Message = HeadUpdate;
+
+    /// Broadcasts a head-status update to any subscribed light clients. Having no
+    /// subscribers yet is normal, not an error.
+    async fn send_message(&self, message: Self::Message) -> Result<(), anyhow::Error> {
+        let _ = self.outgoing.send(message);
+        Ok(())
+    }
+
+    /// Adopts an incoming head-status update after validating it against what's already
+    /// been received, so a light client can't be tricked into moving backward or onto an
+    /// unrelated branch.
+    async fn receive_message(&self, message: Self::Message) -> Result<(), anyhow::Error> {
+        match message {
+            HeadUpdate::FinalityUpdate { header, commitment: _ } => {
+                let mut finalized = self.finalized.write().await;
+                if let Some(current) = finalized.as_ref() {
+                    if header.height <= current.height {
+                        // Stale or out-of-order: drop silently.
+                        return Ok(());
+                    }
+                    if header.parent_id != current.id {
+                        return Err(anyhow::anyhow!(
+                            "finality update {:?} does not chain from current finalized head {:?}",
+                            header.id,
+                            current.id
+                        ));
+                    }
+                }
+                *finalized = Some(header);
+            }
+            HeadUpdate::OptimisticUpdate(header) => {
+                let finalized = self.finalized.read().await;
+                if let Some(current) = finalized.as_ref() {
+                    if header.height <= current.height {
+                        return Ok(());
+                    }
+                }
+                drop(finalized);
+
+                let mut optimistic = self.optimistic.write().await;
+                if let Some(current) = optimistic.as_ref() {
+                    if header.height <= current.height {
+                        return Ok(());
+                    }
+                }
+                *optimistic = Some(header);
+            }
+        }
+        Ok(())
+    }
+}
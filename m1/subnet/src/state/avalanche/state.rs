@@ -5,9 +5,13 @@ use std::{
     sync::Arc,
 };
 use avalanche_types::{choices, ids, subnet};
+use chrono::{Duration, Utc};
 use serde::{Deserialize, Serialize};
+use movement_sdk::{da_backend::LocalDaBackend, DaBackend, MessagingLayer};
 use tokio::sync::RwLock;
 use crate::util::types::block::Block;
+use crate::util::types::transaction::Transaction;
+use super::messaging::{BlockHeader, HeadUpdate, LightClientHead};
 
 /// Manages block and chain states for this Vm, both in-memory and persistent.
 #[derive(Clone)]
@@ -24,30 +28,115 @@ pub struct State {
 
     pub preferred : ids::Id,
 
+    /// How hard `verify_block` works on each incoming block. Relaxed while fast-syncing
+    /// toward `verification_edge`, then reverts to `Full` for everything after.
+    pub verification_level: Arc<RwLock<VerificationLevel>>,
+
+    /// Once the block with this id has been accepted, `verification_level` reverts to
+    /// `Full`. `None` means there is no checkpoint to sync toward.
+    pub verification_edge: Option<ids::Id>,
+
+    /// Bounds how far `set_preferred` will walk back looking for a common ancestor
+    /// between the old and new preferred blocks before giving up on the reorg.
+    pub max_reorg_depth: usize,
+
+    /// Emits `OptimisticUpdate`/`FinalityUpdate` messages so light clients can follow
+    /// the chain head without verifying full blocks themselves.
+    pub messaging: LightClientHead,
+
+    /// Where accepted blocks are published and fetched from. Selected at construction so
+    /// a backend other than the default in-memory one can be dropped in without touching
+    /// the rest of the pipeline.
+    pub da_backend: Arc<dyn DaBackend + Send + Sync>,
 
 }
 
 impl Default for State {
     fn default() -> State {
+        // Shared with `da_backend` below: the default `LocalDaBackend` persists published
+        // blocks into this same `db`, rather than a bare in-process map, so they survive
+        // a restart the same way accepted blocks always have.
+        let db: Arc<RwLock<Box<dyn subnet::rpc::database::Database + Send + Sync>>> =
+            Arc::new(RwLock::new(Box::new(subnet::rpc::database::memdb::Database::new())));
+
         Self {
-            db: Arc::new(RwLock::new(subnet::rpc::database::memdb::Database::new())),
+            da_backend: Arc::new(LocalDaBackend::new(db.clone())),
+            db,
             verified_blocks: Arc::new(RwLock::new(HashMap::new())),
             preferred : ids::Id::empty(),
+            verification_level: Arc::new(RwLock::new(VerificationLevel::Full)),
+            verification_edge: None,
+            max_reorg_depth: DEFAULT_MAX_REORG_DEPTH,
+            messaging: LightClientHead::new(),
         }
     }
 }
 
+impl State {
+    /// Builds a `State` that publishes and fetches blocks through `da_backend` instead of
+    /// the default in-memory one, e.g. to point at an external blob store.
+    pub fn with_da_backend(da_backend: Arc<dyn DaBackend + Send + Sync>) -> Self {
+        Self {
+            da_backend,
+            ..Self::default()
+        }
+    }
+
+    /// Builds a `State` over a possibly pre-existing `db`, e.g. one reopened across a
+    /// restart, restoring `verification_level` from it so a node that was fast-syncing at
+    /// `Header`/`None` resumes there instead of silently reverting to `Full`.
+    pub async fn with_db(
+        db: Arc<RwLock<Box<dyn subnet::rpc::database::Database + Send + Sync>>>,
+    ) -> io::Result<Self> {
+        let state = Self {
+            da_backend: Arc::new(LocalDaBackend::new(db.clone())),
+            db,
+            verified_blocks: Arc::new(RwLock::new(HashMap::new())),
+            preferred: ids::Id::empty(),
+            verification_level: Arc::new(RwLock::new(VerificationLevel::Full)),
+            verification_edge: None,
+            max_reorg_depth: DEFAULT_MAX_REORG_DEPTH,
+            messaging: LightClientHead::new(),
+        };
+        state.restore_verification_level().await?;
+        Ok(state)
+    }
+}
+
+/// Default bound on how many blocks `set_preferred` will walk back per side while
+/// searching for a common ancestor.
+const DEFAULT_MAX_REORG_DEPTH: usize = 1024;
+
 const LAST_ACCEPTED_BLOCK_KEY: &[u8] = b"last_accepted_block";
 
-const STATUS_PREFIX: u8 = 0x0;
+const VERIFICATION_LEVEL_KEY: &[u8] = b"verification_level";
+
+const DA_POINTER_PREFIX: u8 = 0x2;
+
+/// Pre-chunk0-5 key prefix: block bytes used to be stored directly under this key
+/// instead of behind a `DaPointer` indirection. Kept only so `get_block` can migrate
+/// entries written before the `DaBackend` split existed; all new writes use
+/// `DA_POINTER_PREFIX`, which is deliberately a different value so the two schemes never
+/// alias the same key.
+const LEGACY_BLOCK_PREFIX: u8 = 0x0;
 
 const DELIMITER: u8 = b'/';
 
-/// Returns a vec of bytes used as a key for identifying blocks in state.
-/// 'STATUS_PREFIX' + 'BYTE_DELIMITER' + [block_id]
-fn block_with_status_key(blk_id: &ids::Id) -> Vec<u8> {
+/// Returns a vec of bytes used as the db key under which a block's `DaPointer` is stored.
+/// 'DA_POINTER_PREFIX' + 'DELIMITER' + [block_id]
+fn da_pointer_key(blk_id: &ids::Id) -> Vec<u8> {
+    let mut k: Vec<u8> = Vec::with_capacity(ids::LEN + 2);
+    k.push(DA_POINTER_PREFIX);
+    k.push(DELIMITER);
+    k.extend_from_slice(&blk_id.to_vec());
+    k
+}
+
+/// Returns the pre-chunk0-5 db key a block's full `BlockWithStatus` bytes used to be
+/// stored under directly. 'LEGACY_BLOCK_PREFIX' + 'DELIMITER' + [block_id]
+fn legacy_block_key(blk_id: &ids::Id) -> Vec<u8> {
     let mut k: Vec<u8> = Vec::with_capacity(ids::LEN + 2);
-    k.push(STATUS_PREFIX);
+    k.push(LEGACY_BLOCK_PREFIX);
     k.push(DELIMITER);
     k.extend_from_slice(&blk_id.to_vec());
     k
@@ -91,6 +180,58 @@ pub enum VerificationStatus {
     TimestampGreaterThanLocal,
 }
 
+/// How much work `verify_block` does per block. Lets a node fast-sync from a trusted
+/// checkpoint instead of re-deriving state transitions for every historical block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationLevel {
+    /// Parent/height linkage and both timestamp checks (against the parent and against
+    /// local time) run. `verify_block` does not check state transitions at any level.
+    Full,
+    /// Only parent/height linkage is checked; the timestamp checks are skipped.
+    Header,
+    /// Any structurally well-formed block is accepted without further checks.
+    None,
+}
+
+impl VerificationLevel {
+    fn to_byte(self) -> u8 {
+        match self {
+            VerificationLevel::Full => 0,
+            VerificationLevel::Header => 1,
+            VerificationLevel::None => 2,
+        }
+    }
+
+    fn from_byte(b: u8) -> Self {
+        match b {
+            1 => VerificationLevel::Header,
+            2 => VerificationLevel::None,
+            _ => VerificationLevel::Full,
+        }
+    }
+}
+
+/// The path between two blocks on possibly-different branches, expressed as the blocks
+/// to undo (`retracted`, ordered from the old tip down toward the ancestor) and the
+/// blocks to apply (`enacted`, ordered from just above the ancestor up to the new tip).
+#[derive(Debug, Clone)]
+pub struct TreeRoute {
+    pub retracted: Vec<ids::Id>,
+    pub enacted: Vec<ids::Id>,
+    pub common_ancestor: ids::Id,
+}
+
+/// What changed as a result of `set_preferred` moving the canonical chain.
+#[derive(Debug, Clone)]
+pub struct ReorgResult {
+    /// The new canonical chain, from just above the common ancestor up to the new
+    /// preferred block.
+    pub canonized_blocks: Vec<ids::Id>,
+    /// Transactions from retracted blocks that need to be re-submitted to the
+    /// `SequencerLayer` since they are no longer part of the canonical chain.
+    pub transactions_to_reverify: Vec<Transaction>,
+}
+
 impl State {
 
     /// Persists the last accepted block Id to state.
@@ -151,34 +292,75 @@ impl State {
         verified_blocks.contains_key(blk_id)
     }
 
-    /// Writes a block to the state storage.
+    /// Publishes a block to the configured `DaBackend` and records the returned pointer
+    /// in `db`, keyed by block id.
     pub async fn write_block(&mut self, block: &Block) -> io::Result<()> {
         let blk_id = block.id();
         let blk_bytes = block.to_slice()?;
 
-        let mut db = self.db.write().await;
-
         let blk_status = BlockWithStatus {
             block_bytes: blk_bytes,
             status: block.status(),
         };
         let blk_status_bytes = blk_status.encode()?;
 
-        db.put(&block_with_status_key(&blk_id), &blk_status_bytes)
+        let pointer = self
+            .da_backend
+            .publish(blk_status_bytes)
             .await
-            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to put block: {:?}", e)))
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("failed to publish block to DA backend: {}", e),
+                )
+            })?;
+
+        let mut db = self.db.write().await;
+        db.put(&da_pointer_key(&blk_id), &pointer)
+            .await
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to put DA pointer: {:?}", e)))
     }
 
-    /// Reads a block from the state storage using the block_with_status_key.
+    /// Reads a block, consulting `verified_blocks` first, then the local pointer map,
+    /// then falling back to fetching it from the `DaBackend`. Blocks written before
+    /// chunk0-5 (no recorded pointer) are transparently migrated into the new format.
     pub async fn get_block(&self, blk_id: &ids::Id) -> io::Result<Block> {
         // check if the block exists in memory as previously verified.
         let verified_blocks = self.verified_blocks.read().await;
         if let Some(b) = verified_blocks.get(blk_id) {
             return Ok(b.clone());
         }
+        drop(verified_blocks);
+
         let db = self.db.read().await;
+        let pointer = match db.get(&da_pointer_key(blk_id)).await {
+            Ok(pointer) => pointer,
+            Err(e) => {
+                if !subnet::rpc::errors::is_not_found(&e) {
+                    return Err(e);
+                }
+                // No pointer recorded. This is expected for a block written before
+                // chunk0-5 introduced the `DaBackend` indirection, when full block bytes
+                // were stored directly under `legacy_block_key`.
+                let legacy_bytes = db.get(&legacy_block_key(blk_id)).await?;
+                drop(db);
+                return self.migrate_legacy_block(blk_id, legacy_bytes).await;
+            }
+        };
+        drop(db);
+
+        let blk_status_bytes = self
+            .da_backend
+            .fetch(&pointer)
+            .await
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("failed to fetch block from DA backend: {}", e),
+                )
+            })?
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "DA backend has no block for pointer"))?;
 
-        let blk_status_bytes = db.get(&block_with_status_key(blk_id)).await?;
         let blk_status = BlockWithStatus::from_slice(blk_status_bytes)?;
 
         let mut blk = Block::from_slice(&blk_status.block_bytes)?;
@@ -187,7 +369,81 @@ impl State {
         Ok(blk)
     }
 
-    pub async fn verify_block(&self, block: &Block) -> io::Result<VerificationStatus, anyhow::Error> {
+    /// Upgrades a block written before chunk0-5 into the current format: republishes its
+    /// bytes through the configured `DaBackend`, records the returned pointer, and drops
+    /// the superseded legacy entry so this only runs once per block.
+    async fn migrate_legacy_block(&self, blk_id: &ids::Id, legacy_bytes: Vec<u8>) -> io::Result<Block> {
+        let blk_status = BlockWithStatus::from_slice(&legacy_bytes)?;
+
+        let pointer = self
+            .da_backend
+            .publish(legacy_bytes)
+            .await
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("failed to publish migrated block to DA backend: {}", e),
+                )
+            })?;
+
+        let mut db = self.db.write().await;
+        db.put(&da_pointer_key(blk_id), &pointer).await.map_err(|e| {
+            Error::new(ErrorKind::Other, format!("failed to put DA pointer: {:?}", e))
+        })?;
+        // Best-effort: the pointer now takes priority on every future read regardless, so
+        // a backend without a working delete just leaves the superseded entry in place.
+        let _ = db.delete(&legacy_block_key(blk_id)).await;
+        drop(db);
+
+        let mut blk = Block::from_slice(&blk_status.block_bytes)?;
+        blk.set_status(blk_status.status);
+
+        Ok(blk)
+    }
+
+    /// Returns the currently active verification level.
+    pub async fn verification_level(&self) -> VerificationLevel {
+        *self.verification_level.read().await
+    }
+
+    /// Persists the current verification level, alongside the last accepted block, so a
+    /// restart resumes at the same level rather than re-verifying from `Full`.
+    async fn persist_verification_level(&self) -> io::Result<()> {
+        let level = self.verification_level().await;
+        let mut db = self.db.write().await;
+        db.put(VERIFICATION_LEVEL_KEY, &[level.to_byte()])
+            .await
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("failed to put verification level: {:?}", e),
+                )
+            })
+    }
+
+    /// Loads the persisted verification level into memory. Call this once on startup,
+    /// after the db is opened, to resume fast-sync across restarts. Defaults to `Full`
+    /// if nothing has been persisted yet.
+    pub async fn restore_verification_level(&self) -> io::Result<()> {
+        let db = self.db.read().await;
+        let level = match db.get(VERIFICATION_LEVEL_KEY).await {
+            Ok(d) => VerificationLevel::from_byte(*d.get(0).unwrap_or(&0)),
+            Err(e) => {
+                if subnet::rpc::errors::is_not_found(&e) {
+                    VerificationLevel::Full
+                } else {
+                    return Err(e);
+                }
+            }
+        };
+        drop(db);
+
+        let mut current = self.verification_level.write().await;
+        *current = level;
+        Ok(())
+    }
+
+    pub async fn verify_block(&self, block: &Block) -> Result<VerificationStatus, anyhow::Error> {
 
         // todo: double check that blindly accepting genesis block does not causes issues
         if block.height() == 0 && block.parent_id() == ids::Id::empty() {
@@ -200,6 +456,13 @@ impl State {
             return Ok(VerificationStatus::AlreadyAdded);
         }
 
+        let level = self.verification_level().await;
+
+        // `None` trusts that the block is structurally well-formed and skips parent,
+        // height, and timestamp checks entirely.
+        if level == VerificationLevel::None {
+            return Ok(VerificationStatus::Verified);
+        }
 
         let parent_blk = self.get_block(&block.parent_id()).await?;
         // ensure the height of the block is immediately following its parent
@@ -207,9 +470,14 @@ impl State {
             return Ok(VerificationStatus::InvalidBlockHeight);
         }
 
+        // `Header` stops at parent/height linkage; only `Full` checks timestamps.
+        if level == VerificationLevel::Header {
+            return Ok(VerificationStatus::Verified);
+        }
+
         // ensure block timestamp is after its parent
         if parent_blk.timestamp() > block.timestamp() {
-            return Ok(VerificationStatus::TimestampGreaterThanLocal);
+            return Ok(VerificationStatus::TimestampGreaterThanParent);
         }
 
         // ensure block timestamp is no more than an hour ahead of this nodes time
@@ -218,28 +486,155 @@ impl State {
         }
 
         Ok(VerificationStatus::Verified)
-        
+
     }
 
-    // Set preferred
-    pub async fn set_preferred(&mut self, blk_id: &ids::Id) -> io::Result<()> {
-        self.preferred = blk_id.clone();
-        Ok(())
+    /// Walks parents of `old_id` and `new_id` back to their common ancestor, bounding the
+    /// walk at `max_depth` per side so an unrelated or pathologically deep branch errors
+    /// out instead of looping forever.
+    async fn tree_route(
+        &self,
+        old_id: &ids::Id,
+        new_id: &ids::Id,
+        max_depth: usize,
+    ) -> Result<TreeRoute, anyhow::Error> {
+        let mut retracted = Vec::new();
+        let mut enacted = Vec::new();
+
+        let mut old_blk = self.get_block(old_id).await?;
+        let mut new_blk = self.get_block(new_id).await?;
+        // Tracked per side rather than as one shared counter, so the bound applies to each
+        // branch on its own: two branches that are each at most `max_depth` deep don't
+        // trip it just because their combined length exceeds it.
+        let mut old_steps = 0usize;
+        let mut new_steps = 0usize;
+
+        macro_rules! bump_steps {
+            ($steps:expr) => {
+                $steps += 1;
+                if $steps > max_depth {
+                    return Err(anyhow::anyhow!(
+                        "tree route between {:?} and {:?} exceeded max depth {} without finding a common ancestor",
+                        old_id, new_id, max_depth
+                    ));
+                }
+            };
+        }
+
+        // Equalize heights first, recording the longer branch's blocks as we go.
+        while old_blk.height() > new_blk.height() {
+            retracted.push(old_blk.id());
+            old_blk = self.get_block(&old_blk.parent_id()).await?;
+            bump_steps!(old_steps);
+        }
+        while new_blk.height() > old_blk.height() {
+            enacted.push(new_blk.id());
+            new_blk = self.get_block(&new_blk.parent_id()).await?;
+            bump_steps!(new_steps);
+        }
+
+        // Walk both branches back together until they meet.
+        while old_blk.id() != new_blk.id() {
+            retracted.push(old_blk.id());
+            enacted.push(new_blk.id());
+            old_blk = self.get_block(&old_blk.parent_id()).await?;
+            new_blk = self.get_block(&new_blk.parent_id()).await?;
+            bump_steps!(old_steps);
+            bump_steps!(new_steps);
+        }
+
+        enacted.reverse();
+
+        Ok(TreeRoute {
+            retracted,
+            enacted,
+            common_ancestor: old_blk.id(),
+        })
+    }
+
+    /// Sets the preferred block, computing a `TreeRoute` against the previous preferred
+    /// block so callers get a precise diff to replay rather than a pointer swap. Retracted
+    /// blocks' transactions are returned so they can be re-submitted to the
+    /// `SequencerLayer`.
+    pub async fn set_preferred(&mut self, blk_id: &ids::Id) -> Result<ReorgResult, anyhow::Error> {
+        let old_preferred = self.preferred;
+        self.preferred = *blk_id;
+
+        if old_preferred == ids::Id::empty() || old_preferred == *blk_id {
+            let preferred_block = self.get_block(blk_id).await?;
+            self.messaging
+                .send_message(HeadUpdate::OptimisticUpdate(BlockHeader::from_block(
+                    &preferred_block,
+                )))
+                .await?;
+
+            return Ok(ReorgResult {
+                canonized_blocks: vec![*blk_id],
+                transactions_to_reverify: Vec::new(),
+            });
+        }
+
+        let route = self
+            .tree_route(&old_preferred, blk_id, self.max_reorg_depth)
+            .await?;
+
+        let mut transactions_to_reverify = Vec::new();
+        for retracted_id in &route.retracted {
+            let retracted_block = self.get_block(retracted_id).await?;
+            transactions_to_reverify.extend(retracted_block.transactions());
+        }
+
+        let preferred_block = self.get_block(blk_id).await?;
+        self.messaging
+            .send_message(HeadUpdate::OptimisticUpdate(BlockHeader::from_block(
+                &preferred_block,
+            )))
+            .await?;
+
+        Ok(ReorgResult {
+            canonized_blocks: route.enacted,
+            transactions_to_reverify,
+        })
     }
 
     // Accept block should only accept a fully built block
     pub async fn accept_block(&mut self, block: &Block) -> io::Result<()> {
-        block.set_status(status::Status::Accepted);
+        block.set_status(choices::status::Status::Accepted);
         self.write_block(block).await?;
         self.set_last_accepted_block(&block.id()).await?;
-        self.remove_verified(blk_id).await;
+        self.remove_verified(&block.id()).await;
+
+        // Reaching the checkpoint: the trusted edge has now been accepted, so resume
+        // full verification for everything that follows it.
+        if self.verification_edge.as_ref() == Some(&block.id()) {
+            let mut level = self.verification_level.write().await;
+            *level = VerificationLevel::Full;
+        }
+        self.persist_verification_level().await?;
+
+        self.messaging
+            .send_message(HeadUpdate::FinalityUpdate {
+                header: BlockHeader::from_block(block),
+                commitment: block.status(),
+            })
+            .await
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("failed to send finality update: {}", e),
+                )
+            })?;
+
+        Ok(())
     }
 
     // Reject block should write a block to the db with a rejected status
     pub async fn reject_block(&mut self, block : &Block) -> io::Result<()> {
-        block.set_status(status::Status::Rejected);
+        block.set_status(choices::status::Status::Rejected);
         self.write_block(block).await?; // blocks are written to the db when rejected for further rejection
-        self.remove_verified(blk_id).await;
+        self.remove_verified(&block.id()).await;
+
+        Ok(())
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,223 @@
+//! A parallel block-verification queue sitting between the `DataAvailabilityLayer` and the
+//! `ExecutionLayer`.
+//!
+//! `State::verify_block` used to run synchronously on whichever task pulled a block from the
+//! DA layer, so ingestion could not overlap verification. `BlockQueue` decouples the two:
+//! blocks land in `unverified`, a fixed pool of workers verifies them against [`State`], and
+//! `ExecutionLayer::get_next_block` wakes as soon as something lands in `verified`.
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use avalanche_types::ids;
+use indexmap::{IndexMap, IndexSet};
+use tokio::sync::{Notify, RwLock};
+
+use crate::state::avalanche::state::{State, VerificationStatus};
+use crate::util::types::block::Block;
+
+/// A point-in-time depth reading for each of the queue's three stages.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockQueueInfo {
+    pub unverified_queue_size: usize,
+    pub verifying_queue_size: usize,
+    pub verified_queue_size: usize,
+}
+
+impl BlockQueueInfo {
+    /// Sum of all three stages, for callers that just want a single backpressure signal.
+    pub fn total_queue_size(&self) -> usize {
+        self.unverified_queue_size + self.verifying_queue_size + self.verified_queue_size
+    }
+}
+
+/// The three pipeline stages, held behind a single lock so moving a block id from one
+/// stage to another (e.g. popping it out of `unverified` and into `verifying`) is one
+/// atomic step. Locking each stage separately would let a second worker observe a block
+/// id in neither set, between the pop and the insert.
+///
+/// `unverified` and `verifying` carry the full `Block` alongside its id: these blocks
+/// haven't been verified or accepted yet, so they exist nowhere `State::get_block` can
+/// resolve them from (`verified_blocks`, `db`, or the DA backend) — the queue is their
+/// only copy until a worker verifies them and hands them to `State::add_verified`.
+#[derive(Default)]
+struct Queues {
+    unverified: IndexMap<ids::Id, Block>,
+    verifying: HashMap<ids::Id, Block>,
+    verified: IndexSet<ids::Id>,
+}
+
+/// Owns the unverified/verifying/verified pipeline and the worker pool that drains it.
+#[derive(Clone)]
+pub struct BlockQueue {
+    state: State,
+    queues: Arc<RwLock<Queues>>,
+    /// Woken on every pipeline mutation: a new `unverified` entry, a `verifying` removal
+    /// (so a child blocked on its parent can recheck), or a new `verified` entry. Workers
+    /// and `next_verified` treat it as a condvar and re-check their condition on wake,
+    /// rather than busy-spinning with `yield_now`.
+    notify: Arc<Notify>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl BlockQueue {
+    pub fn new(state: State) -> Self {
+        Self {
+            state,
+            queues: Arc::new(RwLock::new(Queues::default())),
+            notify: Arc::new(Notify::new()),
+            shutdown: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Spawns `max(num_cpus - 2, 1)` worker tasks and returns the queue they drain.
+    ///
+    /// Blocks should be pushed in with [`Self::enqueue`] as they arrive from the DA layer.
+    pub fn spawn(state: State) -> Self {
+        let queue = Self::new(state);
+        let worker_count = num_cpus::get().saturating_sub(2).max(1);
+        for _ in 0..worker_count {
+            let worker = queue.clone();
+            tokio::spawn(async move { worker.run_worker().await });
+        }
+        queue
+    }
+
+    /// Adds a block pulled via `get_next_block` to the unverified set. Takes the full
+    /// `Block`, not just its id: nothing upstream of `verify_block` has seen this block
+    /// yet, so `State::get_block` has no way to rematerialize it from `verified_blocks`,
+    /// `db`, or the DA backend until a worker verifies it and calls `add_verified`.
+    pub async fn enqueue(&self, block: Block) {
+        let mut queues = self.queues.write().await;
+        queues.unverified.insert(block.id(), block);
+        drop(queues);
+        self.notify.notify_waiters();
+    }
+
+    /// Returns the next verified block id, in the order it finished verification, if any.
+    pub async fn pop_verified(&self) -> Option<ids::Id> {
+        let mut queues = self.queues.write().await;
+        queues.verified.shift_remove_index(0)
+    }
+
+    /// Waits until at least one block is available in `verified`, then pops it.
+    pub async fn next_verified(&self) -> Option<ids::Id> {
+        loop {
+            // Registered before the check below, so a notification that arrives between
+            // the check and the `.await` isn't missed.
+            let notified = self.notify.notified();
+
+            if let Some(blk_id) = self.pop_verified().await {
+                return Some(blk_id);
+            }
+            if self.shutdown.load(Ordering::SeqCst) {
+                return None;
+            }
+
+            notified.await;
+        }
+    }
+
+    /// Current depth of each stage, for backpressure decisions upstream.
+    pub async fn info(&self) -> BlockQueueInfo {
+        let queues = self.queues.read().await;
+        BlockQueueInfo {
+            unverified_queue_size: queues.unverified.len(),
+            verifying_queue_size: queues.verifying.len(),
+            verified_queue_size: queues.verified.len(),
+        }
+    }
+
+    /// Live total across all three stages.
+    pub async fn total_queue_size(&self) -> usize {
+        self.info().await.total_queue_size()
+    }
+
+    /// Signals workers to drain `unverified` and exit rather than pulling new work.
+    pub fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// Pops the oldest `unverified` entry and marks it `verifying` within the same lock
+    /// acquisition, so a block is never observable in neither set.
+    async fn pop_unverified_and_mark_verifying(&self) -> Option<Block> {
+        let mut queues = self.queues.write().await;
+        let (blk_id, block) = queues.unverified.shift_remove_index(0)?;
+        queues.verifying.insert(blk_id, block.clone());
+        Some(block)
+    }
+
+    async fn run_worker(&self) {
+        loop {
+            let block = loop {
+                let notified = self.notify.notified();
+
+                match self.pop_unverified_and_mark_verifying().await {
+                    Some(block) => break block,
+                    None => {
+                        if self.shutdown.load(Ordering::SeqCst) {
+                            return;
+                        }
+                        notified.await;
+                    }
+                }
+            };
+
+            let blk_id = block.id();
+
+            if let Err(_) = self.verify_one(&block).await {
+                // Verification failures are the caller's concern; drop the id from
+                // `verifying` so it doesn't wedge the pipeline and move on.
+                let mut queues = self.queues.write().await;
+                queues.verifying.remove(&blk_id);
+                drop(queues);
+                self.notify.notify_waiters();
+                continue;
+            }
+
+            // Share the verified block with `State` itself: `verify_block`'s parent
+            // lookup goes through `State::get_block`, which only consults
+            // `verified_blocks`/`db`/the DA backend, none of which this queue otherwise
+            // populates. Without this, a child whose parent only cleared `verifying`
+            // here (and not in `State`) would fail verification with "parent not found".
+            self.state.clone().add_verified(&block).await;
+
+            let mut queues = self.queues.write().await;
+            queues.verifying.remove(&blk_id);
+            queues.verified.insert(blk_id);
+            drop(queues);
+            self.notify.notify_waiters();
+        }
+    }
+
+    /// Verifies a single block, first waiting for its parent to clear `verifying` if needed
+    /// (the verify contract guarantees a parent is verified before its children are). The
+    /// parent itself may already be verified/accepted (resolvable via `State::get_block`)
+    /// or may still be sitting in this same queue, in which case `State::get_block` can't
+    /// see it yet, so the wait below keys off `verifying` rather than trying to fetch it.
+    async fn verify_one(&self, block: &Block) -> Result<(), anyhow::Error> {
+        loop {
+            let notified = self.notify.notified();
+            if !self.queues.read().await.verifying.contains_key(&block.parent_id()) {
+                break;
+            }
+            notified.await;
+        }
+
+        match self.state.verify_block(block).await? {
+            VerificationStatus::InvalidBlockHeight
+            | VerificationStatus::TimestampGreaterThanParent
+            | VerificationStatus::TimestampGreaterThanLocal => {
+                Err(anyhow::anyhow!("block {:?} failed verification", block.id()))
+            }
+            VerificationStatus::Genesis
+            | VerificationStatus::Verified
+            | VerificationStatus::AlreadyAdded => Ok(()),
+        }
+    }
+}
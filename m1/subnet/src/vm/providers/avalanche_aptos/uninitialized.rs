@@ -10,12 +10,19 @@ use super::initialized::Initialized;
 
 // avalanche state
 use crate::state::avalanche::state::State;
+use crate::state::block_queue::BlockQueue;
+use crate::util::types::block::Block;
+use avalanche_types::ids;
 use super::avalanche_aptos::AvalancheAptosState;
 
 #[derive(Debug, Clone)]
 pub struct Uninitialized {
     pub executor : Executor<Aptos<aptos::uninitialized::Uninitialized>>,
     pub state: State,
+    /// Sits between whatever hands this VM blocks (the `DataAvailabilityLayer` side) and
+    /// `executor` (the `ExecutionLayer` side): [`Self::ingest_block`] feeds it, workers
+    /// verify against `state` in parallel, and [`Self::next_verified_block`] drains it.
+    pub block_queue: BlockQueue,
 }
 
 impl AvalancheAptosState for Uninitialized {}
@@ -25,8 +32,20 @@ impl Uninitialized {
     pub fn new(state : State) -> Self {
         Uninitialized {
             executor: Executor::new(Aptos::new(aptos::uninitialized::Uninitialized::default())),
+            block_queue: BlockQueue::spawn(state.clone()),
             state,
         }
     }
 
+    /// Hands a freshly received block to `block_queue` for parallel verification, rather
+    /// than verifying it synchronously on the caller's task.
+    pub async fn ingest_block(&self, block: Block) {
+        self.block_queue.enqueue(block).await;
+    }
+
+    /// Waits for the next verified block id, in the order it finished verification.
+    pub async fn next_verified_block(&self) -> Option<ids::Id> {
+        self.block_queue.next_verified().await
+    }
+
 }
\ No newline at end of file
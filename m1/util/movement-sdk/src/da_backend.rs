@@ -0,0 +1,64 @@
+//! Backends for the [`DaBackend`](crate::DaBackend) trait.
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use avalanche_types::subnet;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{DaBackend, DaPointer};
+
+const BLOB_PREFIX: u8 = 0x1;
+
+const DELIMITER: u8 = b'/';
+
+/// Returns the db key a published blob is stored under: 'BLOB_PREFIX' + 'DELIMITER' +
+/// [pointer].
+fn blob_key(pointer: &DaPointer) -> Vec<u8> {
+    let mut k = Vec::with_capacity(pointer.len() + 2);
+    k.push(BLOB_PREFIX);
+    k.push(DELIMITER);
+    k.extend_from_slice(pointer);
+    k
+}
+
+/// Persists published blocks into `db`, so they survive a process restart the same way
+/// they did before block storage delegated to a `DaBackend`. The default choice when no
+/// external DA backend is wired up; `db` can be disk-backed, so this is not purely
+/// in-process like a bare `HashMap` would be.
+#[derive(Clone)]
+pub struct LocalDaBackend {
+    db: Arc<RwLock<Box<dyn subnet::rpc::database::Database + Send + Sync>>>,
+}
+
+impl LocalDaBackend {
+    pub fn new(db: Arc<RwLock<Box<dyn subnet::rpc::database::Database + Send + Sync>>>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl DaBackend for LocalDaBackend {
+    async fn publish(&self, block_bytes: Vec<u8>) -> Result<DaPointer, anyhow::Error> {
+        let pointer = Uuid::new_v4().as_bytes().to_vec();
+        let mut db = self.db.write().await;
+        db.put(&blob_key(&pointer), &block_bytes)
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to put DA blob: {:?}", e))?;
+        Ok(pointer)
+    }
+
+    async fn fetch(&self, pointer: &DaPointer) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        let db = self.db.read().await;
+        match db.get(&blob_key(pointer)).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) => {
+                if subnet::rpc::errors::is_not_found(&e) {
+                    Ok(None)
+                } else {
+                    Err(anyhow::anyhow!("failed to fetch DA blob: {:?}", e))
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use async_trait::async_trait;
 
+pub mod da_backend;
+
 // Top-level definition of traits.
 // Complex extensions and integrations should be defined in the submodules.
 #[async_trait]
@@ -79,6 +81,32 @@ pub trait DataAvailabilityLayer {
 
 }
 
+/// Opaque handle a [`DaBackend`] returns from `publish`, used to `fetch` the same bytes
+/// back later. A `Vec<u8>` rather than an associated type so backends stay interchangeable
+/// behind a `dyn DaBackend` trait object (`State` holds an `Arc<dyn DaBackend>`, much as
+/// it holds its database as a boxed trait object).
+pub type DaPointer = Vec<u8>;
+
+/// Pluggable storage/transport a [`DataAvailabilityLayer`] implementation publishes
+/// encoded blocks to and retrieves them from, kept separate from the execution pipeline
+/// so new backends (e.g. an external blob store) can be dropped in without touching it.
+#[async_trait]
+pub trait DaBackend {
+
+    // Publishes encoded block bytes and returns a pointer to retrieve them by.
+    async fn publish(
+        &self,
+        block_bytes: Vec<u8>
+    ) -> Result<DaPointer, anyhow::Error>;
+
+    // Retrieves previously published block bytes, or `None` if the pointer is unknown.
+    async fn fetch(
+        &self,
+        pointer: &DaPointer
+    ) -> Result<Option<Vec<u8>>, anyhow::Error>;
+
+}
+
 #[async_trait]
 pub trait ExecutionLayer {
 